@@ -0,0 +1,17 @@
+//! Helpers shared by the examples.
+//!
+//! Not an example itself; each example pulls it in with
+//! `#[path = "common.rs"] mod common;`.
+
+use rand::{RngCore, rngs::OsRng};
+
+/// Generate a random swig_id from the operating system's CSPRNG.
+///
+/// The swig_id seeds the wallet's PDA addresses, so it must be unguessable:
+/// a predictable id lets anyone derive (and front-run the creation of) the
+/// wallet address. `OsRng` makes the secure source explicit.
+pub fn generate_swig_id() -> [u8; 32] {
+    let mut swig_id = [0u8; 32];
+    OsRng.fill_bytes(&mut swig_id);
+    swig_id
+}
@@ -5,7 +5,9 @@
 //!
 //! Run with: `cargo run --example ed25519_wallet`
 
-use rand::Rng;
+#[path = "common.rs"]
+mod common;
+
 use solana_client::rpc_client::RpcClient;
 use solana_keypair::Keypair;
 use solana_sdk::{pubkey::Pubkey, system_instruction, transaction::Transaction};
@@ -31,7 +33,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n=== Creating New Wallet ===");
 
     // Generate a random swig_id (32 bytes)
-    let swig_id: [u8; 32] = rand::thread_rng().r#gen();
+    let swig_id = common::generate_swig_id();
     println!("Swig ID: {}", hex::encode(swig_id));
 
     // Create Ed25519 client role from authority's public key
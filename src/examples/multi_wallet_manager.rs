@@ -9,18 +9,90 @@
 //!
 //! Run with: `cargo run --example multi_wallet_manager`
 
-use rand::Rng;
+#[path = "common.rs"]
+mod common;
+
 use solana_client::rpc_client::RpcClient;
 use solana_keypair::Keypair;
 use solana_sdk::{
-    instruction::Instruction, pubkey::Pubkey, system_instruction, transaction::Transaction,
+    commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey,
+    signature::Signature, system_instruction, transaction::Transaction,
 };
 use solana_signer::{EncodableKey, Signer};
-use std::path::Path;
-use swig_sdk::{BatchConfig, BatchStrategy, Ed25519ClientRole, MultiWalletManager, SwigWallet};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+use swig_sdk::{
+    BatchConfig, BatchResult, BatchStrategy, Ed25519ClientRole, MultiWalletManager, Permission,
+    SwigWallet, authority::AuthorityType, types::UpdateAuthorityData,
+};
 
 const RPC_URL: &str = "https://api.devnet.solana.com";
 
+/// Fee for a funding transaction: one signature at the base fee.
+const FUNDING_TX_FEE: u64 = 5_000;
+
+/// Maximum number of signatures accepted by a single `getSignatureStatuses` call.
+const MAX_SIGNATURE_STATUSES: usize = 100;
+
+/// Last observed status of a signature while waiting for confirmation.
+enum ConfirmStatus {
+    /// Not yet seen by the node.
+    Pending,
+    Processed,
+    Confirmed,
+    Finalized,
+    Failed(String),
+}
+
+impl fmt::Display for ConfirmStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfirmStatus::Pending => write!(f, "pending"),
+            ConfirmStatus::Processed => write!(f, "processed"),
+            ConfirmStatus::Confirmed => write!(f, "confirmed"),
+            ConfirmStatus::Finalized => write!(f, "finalized"),
+            ConfirmStatus::Failed(err) => write!(f, "failed: {}", err),
+        }
+    }
+}
+
+/// Outcome of topping up one wallet before a batch.
+enum FundingOutcome {
+    /// The wallet was below the threshold and received this many lamports.
+    Funded(u64),
+    /// The wallet already held at least the threshold; skipped.
+    AboveThreshold,
+    /// Reading the balance or the funding transfer failed.
+    Failed(String),
+}
+
+/// Outcome of removing an identity from one wallet.
+enum RemovalOutcome {
+    /// Every role with the identity was removed (one signature per role).
+    Removed(Vec<Signature>),
+    /// The wallet has no role with the identity.
+    NotPresent,
+    /// Loading the wallet or a removal failed; earlier removals may have landed.
+    Failed(String),
+}
+
+/// Outcome of ensuring a role has a permission in one wallet.
+enum EnsureOutcome {
+    /// The permission was missing and has been added.
+    Added(Signature),
+    /// The role already has the permission (or `All` covering it); skipped.
+    AlreadyPresent,
+    /// The wallet has no role with the identity; skipped.
+    RoleNotFound,
+    /// Loading the wallet or the update failed.
+    Failed(String),
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let authority = load_or_create_keypair("authority.json");
@@ -35,9 +107,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut wallet_ids: Vec<([u8; 32], u32)> = Vec::new();
     let mut wallet_addresses: Vec<Pubkey> = Vec::new();
+    // Human-readable names used in logs and reports instead of raw hex ids
+    let mut labels: HashMap<[u8; 32], String> = HashMap::new();
 
     for i in 0..3 {
-        let swig_id: [u8; 32] = rand::thread_rng().r#gen();
+        let swig_id = common::generate_swig_id();
 
         let wallet = SwigWallet::builder()
             .with_swig_id(swig_id)
@@ -50,7 +124,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let info = wallet.get_info()?;
         wallet_addresses.push(info.swig_wallet_address);
 
-        println!("Created wallet {}: {}", i + 1, hex::encode(swig_id));
+        let label = format!("wallet-{}", i + 1);
+        println!("Created {}: {}", label, hex::encode(swig_id));
+        labels.insert(swig_id, label);
         wallet_ids.push((swig_id, 0)); // (swig_id, role_id)
     }
 
@@ -60,7 +136,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n=== Funding All Wallets ===");
 
     let fund_amount = 10_000_000; // 0.01 SOL each
-    fund_wallet(&rpc_client, &authority, &wallet_addresses, fund_amount)?;
+
+    // Fee payers for large runs can be kept as keypair files in a directory.
+    // Funding is spread across them round-robin; otherwise the authority pays.
+    let fee_payer_dir = Path::new("fee_payers");
+    let fee_payers = if fee_payer_dir.is_dir() {
+        load_fee_payers(&rpc_client, fee_payer_dir)?
+    } else {
+        Vec::new()
+    };
+
+    let fee_payers = funding_payers(&fee_payers, wallet_addresses.len(), fund_amount);
+    if fee_payers.is_empty() {
+        fund_wallet(&rpc_client, &authority, &wallet_addresses, fund_amount)?;
+    } else {
+        println!(
+            "Funding from {} fee payers in {}",
+            fee_payers.len(),
+            fee_payer_dir.display()
+        );
+        for (i, fee_payer) in fee_payers.iter().enumerate() {
+            let addresses: Vec<Pubkey> = wallet_addresses
+                .iter()
+                .skip(i)
+                .step_by(fee_payers.len())
+                .copied()
+                .collect();
+            if !addresses.is_empty() {
+                fund_wallet(&rpc_client, fee_payer, &addresses, fund_amount)?;
+            }
+        }
+    }
     println!("Funded all wallets");
 
     // =========================================================================
@@ -68,14 +174,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // =========================================================================
     println!("\n=== Creating MultiWalletManager ===");
 
-    let rpc_client = RpcClient::new(RPC_URL);
     let client_role = Box::new(Ed25519ClientRole::new(authority.pubkey()));
 
     let mut manager = MultiWalletManager::new(
         client_role,
         &authority,       // fee payer
         Some(&authority), // authority keypair (for Ed25519)
-        rpc_client,
+        RpcClient::new(RPC_URL),
     );
 
     println!("Manager created for {} wallets", wallet_ids.len());
@@ -115,6 +220,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // =========================================================================
     println!("\n=== Creating SOL Transfer Instructions ===");
 
+    // Fetch the slot once and reuse it for every builder call in this flow,
+    // so all instructions share the same replay-protection slot. Passing
+    // `None` instead makes each call fetch its own (for non-Ed25519).
+    let current_slot = rpc_client.get_slot()?;
+    println!("Using slot {} for instruction building", current_slot);
+
     let recipient = Pubkey::new_unique();
     let amount_per_wallet = 1000; // lamports
 
@@ -122,7 +233,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         wallet_ids.clone(),
         recipient,
         amount_per_wallet,
-        None, // current_slot (auto-fetched for non-Ed25519)
+        Some(current_slot),
     )?;
 
     println!("Created {} instruction batches", sol_instructions.len());
@@ -141,7 +252,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         token_mint,
         token_recipient,
         token_amount,
-        None,
+        Some(current_slot),
     )?;
 
     println!(
@@ -168,7 +279,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 amount,
             ))
         },
-        None,
+        Some(current_slot),
     )?;
 
     println!(
@@ -181,12 +292,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // =========================================================================
     println!("\n=== Executing Batch Operations ===");
 
+    // Top up any wallet that dropped below its initial funding, so the batch
+    // doesn't fail on balance. Wallets that can't be funded are left out.
+    let mut funding_failures = Vec::new();
+    let mut batch_ids = Vec::new();
+    for ((swig_id, role_id), (_, outcome)) in wallet_ids.iter().zip(fund_below_threshold(
+        &rpc_client,
+        &authority,
+        &wallet_addresses,
+        fund_amount,
+    )) {
+        match outcome {
+            FundingOutcome::Funded(lamports) => {
+                println!(
+                    "Topped up {} with {} lamports",
+                    wallet_name(&labels, swig_id),
+                    lamports
+                );
+                batch_ids.push((*swig_id, *role_id));
+            }
+            FundingOutcome::AboveThreshold => batch_ids.push((*swig_id, *role_id)),
+            FundingOutcome::Failed(err) => funding_failures.push((*swig_id, err)),
+        }
+    }
+
     let recipient = Pubkey::new_unique();
     let config = BatchConfig::default().with_num_threads(2);
 
     let result = manager
         .execute_batch(
-            wallet_ids.clone(),
+            batch_ids,
             |_swig_id, _role_id, swig_wallet_address| {
                 Ok(system_instruction::transfer(
                     &swig_wallet_address,
@@ -207,11 +342,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Successful operations: {}", result.successful_count());
     println!("Failed operations: {}", result.failed_count());
 
+    // Funding failures never reached the batch, so report them on their own
+    if !funding_failures.is_empty() {
+        println!("\nFunding failures (not attempted):");
+        for (swig_id, err) in &funding_failures {
+            println!("  {}: {}", wallet_name(&labels, swig_id), err);
+        }
+    }
+
     // Get successful wallet IDs
     let successful_ids = result.successful_swig_ids();
     println!("\nSuccessful wallets:");
     for id in &successful_ids {
-        println!("  {}", hex::encode(id));
+        println!("  {}", wallet_name(&labels, id));
     }
 
     // Get failed wallet IDs
@@ -219,7 +362,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if !failed_ids.is_empty() {
         println!("\nFailed wallets:");
         for id in &failed_ids {
-            println!("  {}", hex::encode(id));
+            println!("  {}", wallet_name(&labels, id));
         }
     }
 
@@ -234,11 +377,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if !result.failed.is_empty() {
         println!("\nFailed operations:");
         for failed in &result.failed {
-            println!("  Wallet: {}", hex::encode(failed.swig_id));
+            println!("  Wallet: {}", wallet_name(&labels, &failed.swig_id));
             println!("  Error: {:?}", failed.error);
         }
     }
 
+    // Operator-friendly reports
+    println!("\nSummary:");
+    print!("{}", summary_table(&result, &labels));
+
+    println!("\nMarkdown report:");
+    print!("{}", to_markdown(&result, &labels));
+
+    let histogram = failed_reasons_histogram(&result);
+    if !histogram.is_empty() {
+        println!("\nFailure reasons:");
+        for (reason, count) in &histogram {
+            println!("  {} x {}", count, reason);
+        }
+    }
+
+    // Wait for every successful batch to reach finalization
+    let signatures: Vec<Signature> = result.successful.iter().map(|b| b.signature).collect();
+    println!(
+        "\nWaiting for {} signatures to finalize...",
+        signatures.len()
+    );
+    for (signature, status) in confirm_signatures(
+        &rpc_client,
+        &signatures,
+        CommitmentConfig::finalized(),
+        Duration::from_secs(60),
+    ) {
+        println!("  {}: {}", signature, status);
+    }
+
     // =========================================================================
     // 10. RETRY FAILED OPERATIONS
     // =========================================================================
@@ -283,6 +456,71 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  - More retries but precise failure detection");
     println!("  - Better for production");
 
+    // =========================================================================
+    // 12. ENSURE A PERMISSION ACROSS WALLETS
+    // =========================================================================
+    println!("\n=== Rolling Out a Permission ===");
+
+    // Give an operator a role on the first two wallets only
+    let operator = Keypair::new();
+    for (swig_id, _) in wallet_ids.iter().take(2) {
+        let mut wallet = SwigWallet::builder()
+            .with_swig_id(*swig_id)
+            .with_client_role(Box::new(Ed25519ClientRole::new(authority.pubkey())))
+            .with_rpc_url(RPC_URL.to_string())
+            .with_fee_payer(&authority)
+            .with_authority_keypair(Some(&authority))
+            .load()?;
+        wallet.add_authority(
+            AuthorityType::Ed25519,
+            operator.pubkey().as_ref(),
+            vec![Permission::Sol {
+                amount: 1_000_000,
+                recurring: None,
+            }],
+        )?;
+    }
+    println!("Added {} to 2 wallets", operator.pubkey());
+
+    // Converge on "the operator can manage authorities"; a second pass
+    // finds nothing left to do
+    let swig_ids: Vec<[u8; 32]> = wallet_ids.iter().map(|(swig_id, _)| *swig_id).collect();
+    for pass in 1..=2 {
+        println!("Pass {}:", pass);
+        for (swig_id, outcome) in ensure_permission(
+            &authority,
+            &swig_ids,
+            operator.pubkey().as_ref(),
+            &Permission::ManageAuthority,
+        ) {
+            let name = wallet_name(&labels, &swig_id);
+            match outcome {
+                EnsureOutcome::Added(signature) => println!("  {}: added ({})", name, signature),
+                EnsureOutcome::AlreadyPresent => println!("  {}: skipped (already present)", name),
+                EnsureOutcome::RoleNotFound => println!("  {}: skipped (no such role)", name),
+                EnsureOutcome::Failed(err) => println!("  {}: failed: {}", name, err),
+            }
+        }
+    }
+
+    // =========================================================================
+    // 13. REMOVE AN AUTHORITY FROM EVERY WALLET
+    // =========================================================================
+    println!("\n=== Offboarding an Authority ===");
+
+    for (swig_id, outcome) in
+        remove_authority_from_all(&authority, &swig_ids, operator.pubkey().as_ref())
+    {
+        let name = wallet_name(&labels, &swig_id);
+        match outcome {
+            RemovalOutcome::Removed(signatures) => {
+                println!("  {}: removed {} roles", name, signatures.len())
+            }
+            RemovalOutcome::NotPresent => println!("  {}: skipped (not present)", name),
+            RemovalOutcome::Failed(err) => println!("  {}: failed: {}", name, err),
+        }
+    }
+
     println!("\n=== Done ===");
     Ok(())
 }
@@ -313,6 +551,406 @@ fn fund_wallet(
     Ok(())
 }
 
+/// Bring every wallet below `threshold` lamports back up to it.
+///
+/// Each wallet is funded in its own transaction from `fee_payer`, so one
+/// failed transfer is attributed to its wallet and does not affect the
+/// others. Outcomes are returned in the order of `wallet_addresses`.
+fn fund_below_threshold(
+    rpc_client: &RpcClient,
+    fee_payer: &Keypair,
+    wallet_addresses: &[Pubkey],
+    threshold: u64,
+) -> Vec<(Pubkey, FundingOutcome)> {
+    wallet_addresses
+        .iter()
+        .map(|address| {
+            let outcome = match rpc_client.get_balance(address) {
+                Ok(balance) if balance >= threshold => FundingOutcome::AboveThreshold,
+                Ok(balance) => {
+                    match fund_wallet(rpc_client, fee_payer, &[*address], threshold - balance) {
+                        Ok(()) => FundingOutcome::Funded(threshold - balance),
+                        Err(err) => FundingOutcome::Failed(err.to_string()),
+                    }
+                }
+                Err(err) => FundingOutcome::Failed(err.to_string()),
+            };
+            (*address, outcome)
+        })
+        .collect()
+}
+
+/// Make sure the role with `identity` has `permission` in each wallet.
+///
+/// Each wallet is loaded as `authority`, which must be allowed to manage
+/// authorities. The permission is added with `update_authority(AddActions)`
+/// only where the role lacks it; wallets where it is already present, or
+/// that have no such role, are skipped. A failure in one wallet does not
+/// stop the others.
+fn ensure_permission(
+    authority: &Keypair,
+    swig_ids: &[[u8; 32]],
+    identity: &[u8],
+    permission: &Permission,
+) -> Vec<([u8; 32], EnsureOutcome)> {
+    swig_ids
+        .iter()
+        .map(|swig_id| {
+            let outcome = ensure_in_wallet(authority, swig_id, identity, permission)
+                .unwrap_or_else(EnsureOutcome::Failed);
+            (*swig_id, outcome)
+        })
+        .collect()
+}
+
+/// Make sure the role with `identity` has `permission` in one wallet.
+fn ensure_in_wallet(
+    authority: &Keypair,
+    swig_id: &[u8; 32],
+    identity: &[u8],
+    permission: &Permission,
+) -> Result<EnsureOutcome, String> {
+    let mut wallet = SwigWallet::builder()
+        .with_swig_id(*swig_id)
+        .with_client_role(Box::new(Ed25519ClientRole::new(authority.pubkey())))
+        .with_rpc_url(RPC_URL.to_string())
+        .with_fee_payer(authority)
+        .with_authority_keypair(Some(authority))
+        .load()
+        .map_err(|err| err.to_string())?;
+
+    let info = wallet.get_info().map_err(|err| err.to_string())?;
+    let Some(role) = info.roles.iter().find(|r| r.authority_identity == identity) else {
+        return Ok(EnsureOutcome::RoleNotFound);
+    };
+    if has_permission(&role.permissions, permission) {
+        return Ok(EnsureOutcome::AlreadyPresent);
+    }
+
+    let signature = wallet
+        .update_authority(
+            role.role_id,
+            UpdateAuthorityData::AddActions(vec![permission.clone()]),
+        )
+        .map_err(|err| err.to_string())?;
+    Ok(EnsureOutcome::Added(signature))
+}
+
+/// Whether `permissions` already grant `wanted`.
+///
+/// `All` grants everything and `AllButManageAuthority` everything except
+/// `ManageAuthority`. Otherwise the permission must be present exactly,
+/// compared by its `Debug` form, which includes every field.
+fn has_permission(permissions: &[Permission], wanted: &Permission) -> bool {
+    let wanted_repr = format!("{:?}", wanted);
+    permissions.iter().any(|perm| match perm {
+        Permission::All => true,
+        Permission::AllButManageAuthority => !matches!(wanted, Permission::ManageAuthority),
+        _ => format!("{:?}", perm) == wanted_repr,
+    })
+}
+
+/// Remove every role with `identity` from each wallet, e.g. when offboarding a key.
+///
+/// Each wallet is loaded as `authority`, which must be allowed to manage
+/// authorities. Wallets without a matching role are skipped, and a failure
+/// in one wallet does not stop the others. Each role is removed in its own
+/// transaction, since the SDK removes one role per `remove_authority` call.
+fn remove_authority_from_all(
+    authority: &Keypair,
+    swig_ids: &[[u8; 32]],
+    identity: &[u8],
+) -> Vec<([u8; 32], RemovalOutcome)> {
+    swig_ids
+        .iter()
+        .map(|swig_id| {
+            let outcome = match remove_matching_roles(authority, swig_id, identity) {
+                Ok(signatures) if signatures.is_empty() => RemovalOutcome::NotPresent,
+                Ok(signatures) => RemovalOutcome::Removed(signatures),
+                Err((signatures, err)) if signatures.is_empty() => RemovalOutcome::Failed(err),
+                Err((signatures, err)) => RemovalOutcome::Failed(format!(
+                    "{} (after removing {} roles)",
+                    err,
+                    signatures.len()
+                )),
+            };
+            (*swig_id, outcome)
+        })
+        .collect()
+}
+
+/// Remove every role with `identity` from one wallet.
+///
+/// On failure, returns the signatures of the removals that already landed
+/// along with the error.
+fn remove_matching_roles(
+    authority: &Keypair,
+    swig_id: &[u8; 32],
+    identity: &[u8],
+) -> Result<Vec<Signature>, (Vec<Signature>, String)> {
+    let mut wallet = SwigWallet::builder()
+        .with_swig_id(*swig_id)
+        .with_client_role(Box::new(Ed25519ClientRole::new(authority.pubkey())))
+        .with_rpc_url(RPC_URL.to_string())
+        .with_fee_payer(authority)
+        .with_authority_keypair(Some(authority))
+        .load()
+        .map_err(|err| (Vec::new(), err.to_string()))?;
+
+    let matching = wallet
+        .get_info()
+        .map_err(|err| (Vec::new(), err.to_string()))?
+        .roles
+        .iter()
+        .filter(|r| r.authority_identity == identity)
+        .count();
+
+    let mut signatures = Vec::with_capacity(matching);
+    for _ in 0..matching {
+        match wallet.remove_authority(identity) {
+            Ok(signature) => signatures.push(signature),
+            Err(err) => return Err((signatures, err.to_string())),
+        }
+    }
+    Ok(signatures)
+}
+
+/// Wait for many signatures to reach `commitment`, polling their statuses in chunks.
+///
+/// Only signatures that have neither failed nor reached `commitment` are
+/// polled again. A chunk whose RPC call fails keeps its last statuses and
+/// is retried on the next poll. Returns each signature's last observed
+/// status once all are resolved, or when `timeout` elapses; anything still
+/// below `commitment` at that point is reported as `Pending`, `Processed`
+/// or `Confirmed`.
+///
+/// Blocks the calling thread between polls, like the other `RpcClient`
+/// calls in this example.
+fn confirm_signatures(
+    rpc_client: &RpcClient,
+    signatures: &[Signature],
+    commitment: CommitmentConfig,
+    timeout: Duration,
+) -> Vec<(Signature, ConfirmStatus)> {
+    let deadline = Instant::now() + timeout;
+    let mut statuses: Vec<(Signature, ConfirmStatus)> = signatures
+        .iter()
+        .map(|signature| (*signature, ConfirmStatus::Pending))
+        .collect();
+    // Indexes into `statuses` still waiting on `commitment`
+    let mut unresolved: Vec<usize> = (0..statuses.len()).collect();
+
+    loop {
+        let mut still_unresolved = Vec::new();
+        for chunk in unresolved.chunks(MAX_SIGNATURE_STATUSES) {
+            let pending: Vec<Signature> = chunk.iter().map(|&i| statuses[i].0).collect();
+            let Ok(response) = rpc_client.get_signature_statuses(&pending) else {
+                still_unresolved.extend_from_slice(chunk);
+                continue;
+            };
+            for (&i, tx_status) in chunk.iter().zip(response.value) {
+                let Some(tx_status) = tx_status else {
+                    still_unresolved.push(i);
+                    continue;
+                };
+                statuses[i].1 = if let Some(err) = &tx_status.err {
+                    ConfirmStatus::Failed(format!("{:?}", err))
+                } else if tx_status.satisfies_commitment(CommitmentConfig::finalized()) {
+                    ConfirmStatus::Finalized
+                } else if tx_status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                    ConfirmStatus::Confirmed
+                } else {
+                    ConfirmStatus::Processed
+                };
+                if tx_status.err.is_none() && !tx_status.satisfies_commitment(commitment) {
+                    still_unresolved.push(i);
+                }
+            }
+        }
+        unresolved = still_unresolved;
+
+        if unresolved.is_empty() || Instant::now() >= deadline {
+            return statuses;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// Display name for a wallet: its label if it has one, otherwise the hex swig_id.
+fn wallet_name(labels: &HashMap<[u8; 32], String>, swig_id: &[u8; 32]) -> String {
+    labels
+        .get(swig_id)
+        .cloned()
+        .unwrap_or_else(|| hex::encode(swig_id))
+}
+
+/// Flatten a batch result into `(wallet name, status, signature or error)` rows.
+fn report_rows(
+    result: &BatchResult,
+    labels: &HashMap<[u8; 32], String>,
+) -> Vec<(String, &'static str, String)> {
+    let mut rows = Vec::new();
+    for batch in &result.successful {
+        for swig_id in &batch.swig_ids {
+            rows.push((
+                wallet_name(labels, swig_id),
+                "success",
+                batch.signature.to_string(),
+            ));
+        }
+    }
+    for failed in &result.failed {
+        rows.push((
+            wallet_name(labels, &failed.swig_id),
+            "failed",
+            format!("{:?}", failed.error),
+        ));
+    }
+    rows
+}
+
+/// Render a batch result as an aligned text table.
+fn summary_table(result: &BatchResult, labels: &HashMap<[u8; 32], String>) -> String {
+    let mut out = format!(
+        "{:<64}  {:<7}  {}\n",
+        "WALLET", "STATUS", "SIGNATURE / ERROR"
+    );
+    for (wallet, status, detail) in report_rows(result, labels) {
+        out.push_str(&format!("{:<64}  {:<7}  {}\n", wallet, status, detail));
+    }
+    out
+}
+
+/// Render a batch result as a Markdown table for pasting into issues or docs.
+fn to_markdown(result: &BatchResult, labels: &HashMap<[u8; 32], String>) -> String {
+    let mut out = String::from("| Wallet | Status | Signature / Error |\n|---|---|---|\n");
+    for (wallet, status, detail) in report_rows(result, labels) {
+        out.push_str(&format!(
+            "| {} | {} | {} |\n",
+            markdown_cell(&wallet),
+            status,
+            markdown_cell(&detail)
+        ));
+    }
+    out
+}
+
+/// Escape text for a Markdown table cell.
+///
+/// Error strings can contain pipes, backticks, and newlines, any of which
+/// would break the table, so cells are escaped rather than code-quoted.
+fn markdown_cell(text: &str) -> String {
+    let mut cell = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '|' | '`' | '*' | '_' | '<' | '>' | '[' | ']' => {
+                cell.push('\\');
+                cell.push(c);
+            }
+            '\n' | '\r' => cell.push(' '),
+            _ => cell.push(c),
+        }
+    }
+    cell
+}
+
+/// Group failures by their underlying cause.
+///
+/// SDK errors wrap RPC and transaction errors, so the outer variant is
+/// nearly always the same. The innermost error in the `source()` chain is
+/// used instead (see `failure_category`). Returns `(reason, count)` sorted
+/// by count, most frequent first, then by reason.
+fn failed_reasons_histogram(result: &BatchResult) -> Vec<(String, usize)> {
+    count_reasons(
+        result
+            .failed
+            .iter()
+            .map(|failed| failure_category(&failed.error)),
+    )
+}
+
+/// Count identical reasons, most frequent first, ties in name order.
+fn count_reasons(reasons: impl IntoIterator<Item = String>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for reason in reasons {
+        *counts.entry(reason).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts
+}
+
+/// Message of the innermost error in `error`'s `source()` chain, truncated
+/// at the first `;` (RPC errors append simulation logs and other
+/// per-transaction detail after it).
+fn failure_category(error: &dyn std::error::Error) -> String {
+    let mut cause = error;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    let cause = cause.to_string();
+    cause
+        .split_once(';')
+        .map_or(cause.as_str(), |(head, _)| head)
+        .trim()
+        .to_string()
+}
+
+/// Load every `*.json` keypair in `dir` as a fee payer, sorted by file name,
+/// together with its current balance.
+fn load_fee_payers(
+    rpc_client: &RpcClient,
+    dir: &Path,
+) -> Result<Vec<(Keypair, u64)>, Box<dyn std::error::Error>> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+
+    let mut fee_payers = Vec::with_capacity(paths.len());
+    for path in paths {
+        let keypair = Keypair::read_from_file(&path)?;
+        let balance = rpc_client.get_balance(&keypair.pubkey())?;
+        fee_payers.push((keypair, balance));
+    }
+    Ok(fee_payers)
+}
+
+/// Fee payers that can each cover their round-robin share of funding
+/// `wallet_count` wallets with `amount` lamports, plus the transaction fee.
+///
+/// Payers that cannot are left out of the rotation with a warning, so one
+/// empty keypair file does not abort funding. Dropping a payer raises the
+/// others' share, so the check repeats until every remaining payer passes.
+fn funding_payers(
+    fee_payers: &[(Keypair, u64)],
+    wallet_count: usize,
+    amount: u64,
+) -> Vec<&Keypair> {
+    let mut eligible: Vec<&(Keypair, u64)> = fee_payers.iter().collect();
+    while !eligible.is_empty() {
+        let share = wallet_count.div_ceil(eligible.len()) as u64;
+        let needed = amount.saturating_mul(share).saturating_add(FUNDING_TX_FEE);
+        let (funded, short): (Vec<_>, Vec<_>) = eligible
+            .into_iter()
+            .partition(|(_, balance)| *balance >= needed);
+        for (keypair, balance) in &short {
+            println!(
+                "Warning: fee payer {} has {} lamports but needs {}; skipping it",
+                keypair.pubkey(),
+                balance,
+                needed
+            );
+        }
+        eligible = funded;
+        if short.is_empty() {
+            break;
+        }
+    }
+    eligible.into_iter().map(|(keypair, _)| keypair).collect()
+}
+
 fn load_or_create_keypair(path: &str) -> Keypair {
     let path = Path::new(path);
     if path.exists() {
@@ -326,3 +964,103 @@ fn load_or_create_keypair(path: &str) -> Keypair {
         keypair
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Error wrapping an optional source, to build `source()` chains.
+    #[derive(Debug)]
+    struct Chained(&'static str, Option<Box<Chained>>);
+
+    impl fmt::Display for Chained {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl std::error::Error for Chained {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.1.as_deref().map(|e| e as _)
+        }
+    }
+
+    #[test]
+    fn failure_category_uses_the_innermost_cause() {
+        let rpc = Chained(
+            "RPC response error -32002: Blockhash not found; 3 log messages",
+            None,
+        );
+        let error = Chained("batch failed", Some(Box::new(rpc)));
+        assert_eq!(
+            failure_category(&error),
+            "RPC response error -32002: Blockhash not found"
+        );
+        assert_eq!(failure_category(&Chained(" timeout ", None)), "timeout");
+    }
+
+    #[test]
+    fn count_reasons_sorts_by_count_then_name() {
+        let reasons = ["b", "a", "c", "c", "b", "c"].map(String::from);
+        assert_eq!(
+            count_reasons(reasons),
+            vec![("c".into(), 3), ("b".into(), 2), ("a".into(), 1)]
+        );
+    }
+
+    #[test]
+    fn funding_payers_drop_payers_that_cannot_cover_their_share() {
+        let payers = vec![
+            (Keypair::new(), 0),
+            (Keypair::new(), 20_000 + FUNDING_TX_FEE),
+            // Enough for one wallet, but not for two once the empty payer is out
+            (Keypair::new(), 10_000 + FUNDING_TX_FEE),
+        ];
+        // Dropping the short payers leaves one payer for all three wallets,
+        // which needs 30_000
+        assert!(funding_payers(&payers, 3, 10_000).is_empty());
+
+        let payers = vec![
+            (Keypair::new(), 0),
+            (Keypair::new(), 30_000 + FUNDING_TX_FEE),
+        ];
+        let funding = funding_payers(&payers, 3, 10_000);
+        assert_eq!(funding.len(), 1);
+        assert_eq!(funding[0].pubkey(), payers[1].0.pubkey());
+    }
+
+    #[test]
+    fn has_permission_respects_all_and_exact_limits() {
+        let sol = |amount| Permission::Sol {
+            amount,
+            recurring: None,
+        };
+        assert!(has_permission(
+            &[Permission::All],
+            &Permission::ManageAuthority
+        ));
+        assert!(!has_permission(
+            &[Permission::AllButManageAuthority],
+            &Permission::ManageAuthority
+        ));
+        assert!(has_permission(
+            &[Permission::AllButManageAuthority],
+            &sol(1)
+        ));
+        assert!(has_permission(
+            &[sol(5), Permission::ManageAuthority],
+            &Permission::ManageAuthority
+        ));
+        assert!(has_permission(&[sol(5)], &sol(5)));
+        assert!(!has_permission(&[sol(5)], &sol(6)));
+    }
+
+    #[test]
+    fn markdown_cell_escapes_table_breaking_text() {
+        assert_eq!(markdown_cell("a|b"), "a\\|b");
+        assert_eq!(markdown_cell("`code`"), "\\`code\\`");
+        assert_eq!(markdown_cell("line 1\nline 2"), "line 1 line 2");
+        assert_eq!(markdown_cell("C:\\path"), "C:\\\\path");
+        assert_eq!(markdown_cell("wallet-1"), "wallet-1");
+    }
+}
@@ -5,6 +5,9 @@
 //!
 //! Run with: `cargo run --example secp256k1_wallet`
 
+#[path = "common.rs"]
+mod common;
+
 use alloy_primitives::B256;
 use alloy_signer::SignerSync;
 use alloy_signer_local::PrivateKeySigner;
@@ -50,7 +53,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // =========================================================================
     println!("\n=== Creating New Wallet ===");
 
-    let swig_id: [u8; 32] = rand::random();
+    let swig_id = common::generate_swig_id();
     println!("Swig ID: {}", hex::encode(swig_id));
 
     let wallet = SwigWallet::builder()
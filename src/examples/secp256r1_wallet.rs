@@ -5,6 +5,9 @@
 //!
 //! Run with: `cargo run --example secp256r1_wallet`
 
+#[path = "common.rs"]
+mod common;
+
 use openssl::{
     bn::BigNumContext,
     ec::{EcGroup, EcKey, PointConversionForm},
@@ -13,7 +16,7 @@ use openssl::{
 };
 use solana_client::rpc_client::RpcClient;
 use solana_keypair::Keypair;
-use solana_sdk::{pubkey::Pubkey, system_instruction, transaction::Transaction};
+use solana_sdk::{hash::hash, pubkey::Pubkey, system_instruction, transaction::Transaction};
 use solana_signer::{EncodableKey, Signer as SolanaSigner};
 use std::path::Path;
 use swig_sdk::{
@@ -35,6 +38,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "Secp256r1 Public Key (compressed): {}",
         hex::encode(&public_key)
     );
+    println!("Fingerprint: {}", fingerprint(&public_key));
 
     // Fee payer (still needs a Solana keypair for transaction fees)
     let fee_payer =
@@ -57,7 +61,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // =========================================================================
     println!("\n=== Creating New Wallet ===");
 
-    let swig_id: [u8; 32] = rand::random();
+    let swig_id = common::generate_swig_id();
     println!("Swig ID: {}", hex::encode(swig_id));
 
     let wallet = SwigWallet::builder()
@@ -68,7 +72,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .create()?;
 
     println!("Wallet created successfully!");
-    wallet.display_swig()?;
+    print_roles(&wallet)?;
 
     // =========================================================================
     // 3. LOAD AN EXISTING WALLET
@@ -116,7 +120,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n=== Adding Secp256r1 Authority ===");
 
     let (new_signing_key, new_public_key) = create_secp256r1_keypair();
-    println!("New authority: {}", hex::encode(&new_public_key));
+    println!(
+        "New authority: {} (fingerprint {})",
+        hex::encode(&new_public_key),
+        fingerprint(&new_public_key)
+    );
 
     let permissions = vec![
         Permission::Sol {
@@ -129,6 +137,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let sig =
         loaded_wallet.add_authority(AuthorityType::Secp256r1, &new_public_key, permissions)?;
     println!("Authority added! Signature: {}", sig);
+    print_roles(&loaded_wallet)?;
 
     // =========================================================================
     // 7. SWITCH TO NEW AUTHORITY
@@ -196,6 +205,26 @@ fn fund_wallet(
     Ok(())
 }
 
+/// List the wallet's on-chain roles with each authority's fingerprint.
+fn print_roles(wallet: &SwigWallet) -> Result<(), Box<dyn std::error::Error>> {
+    for role in wallet.get_info()?.roles {
+        println!(
+            "Role {}: {:?} {}",
+            role.role_id,
+            role.authority_type,
+            fingerprint(&role.authority_identity)
+        );
+    }
+    Ok(())
+}
+
+/// Short, stable identifier for an authority identity.
+/// Base58 of the first 8 bytes of the identity's SHA-256, so a passkey can be
+/// recognized across sessions without reading 33 bytes of hex.
+fn fingerprint(identity: &[u8]) -> String {
+    bs58::encode(&hash(identity).to_bytes()[..8]).into_string()
+}
+
 /// Create a secp256r1 (P-256) keypair using OpenSSL.
 /// Returns the private key and 33-byte compressed public key.
 fn create_secp256r1_keypair() -> (EcKey<Private>, [u8; 33]) {
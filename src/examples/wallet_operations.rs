@@ -5,15 +5,25 @@
 //! - Permission types
 //! - Sub-account operations
 //! - Session management
+//! - Token delegation and sweeping
 //!
 //! Run with: `cargo run --example wallet_operations`
 
-use rand::Rng;
+#[path = "common.rs"]
+mod common;
+
 use solana_client::rpc_client::RpcClient;
 use solana_keypair::Keypair;
-use solana_sdk::{pubkey::Pubkey, system_instruction, transaction::Transaction};
+use solana_sdk::{
+    instruction::Instruction, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+    signature::Signature, system_instruction, transaction::Transaction,
+};
 use solana_signer::{EncodableKey, Signer};
-use std::path::Path;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use std::{collections::HashMap, fmt, path::Path, time::Duration};
 use swig_sdk::{
     authority::AuthorityType, types::UpdateAuthorityData, Ed25519ClientRole, Permission,
     RecurringConfig, SwigWallet,
@@ -21,6 +31,186 @@ use swig_sdk::{
 
 const RPC_URL: &str = "https://api.devnet.solana.com";
 
+/// Token-2022 program. Mints owned by it need their token accounts and
+/// instructions built against this program instead of `spl_token::id()`.
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Approximate slot duration, used to turn slot counts into wall-clock time.
+const SLOT_TIME: Duration = Duration::from_millis(400);
+
+/// Worst-case size of an add/update-authority transaction, excluding the new
+/// role's identity and its permissions:
+/// - signatures: 1 + 2 * 64 (fee payer and acting authority)         = 129
+/// - message header, blockhash and length prefixes: 3 + 32 + 3       =  38
+/// - accounts: fee payer, authority, config, wallet, system program,
+///   swig program, compute budget program, instructions sysvar: 8 * 32 = 256
+/// - compute budget instructions (unit limit + unit price)             =  20
+/// - swig instruction envelope: indexes, lengths, arguments, role id   =  24
+/// - acting authority payload (secp256k1 signature + slot + counter)   =  77
+const AUTHORITY_TX_OVERHEAD: usize = 129 + 38 + 256 + 20 + 24 + 77;
+
+/// Size of the header in front of every encoded permission
+/// (type u16, length u16, boundary u32).
+const PERMISSION_HEADER_SIZE: usize = 8;
+
+/// Error from `add_authority_chunked`.
+///
+/// Carries whatever already landed, so a partially created role can be
+/// found and completed (or removed) by the caller.
+#[derive(Debug)]
+struct ChunkedAddError {
+    /// The new role, if the initial `add_authority` landed and was found.
+    role_id: Option<u32>,
+    /// Signatures of the transactions that landed, in order.
+    signatures: Vec<Signature>,
+    error: String,
+}
+
+impl fmt::Display for ChunkedAddError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.role_id {
+            Some(role_id) => write!(
+                f,
+                "role {} partially added after {} transactions: {}",
+                role_id,
+                self.signatures.len(),
+                self.error
+            ),
+            None => write!(f, "authority not added: {}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for ChunkedAddError {}
+
+/// Worst-case size of a `sign_v2` transaction on top of its inner
+/// instructions measured as a bare message paid by the swig wallet (see
+/// `wrapped_size`). That measurement already covers the message
+/// header, blockhash, swig wallet account, and the inner instructions with
+/// their accounts. The rest of the real transaction is:
+/// - signatures: 1 + 2 * 64 (fee payer and authority)                    = 129
+/// - accounts: fee payer, authority, swig config, swig program, compute
+///   budget program, instructions sysvar, secp256r1 program: 7 * 32        = 224
+/// - compute budget instructions (unit limit + unit price)                =  20
+/// - sign_v2 instruction envelope: indexes, lengths, arguments, role id   =  24
+/// - authority payload (secp256k1 signature + slot + counter)             =  77
+/// - secp256r1 precompile data (offsets, signature, public key, message)  = 150
+///
+/// The last two depend on the authority type; both are counted so the
+/// budget holds for any of them.
+const SIGN_V2_TX_OVERHEAD: usize = 129 + 224 + 20 + 24 + 77 + 150;
+
+/// Result of `sign_v2_multi`.
+struct MultiSignResult {
+    /// Signatures of the transactions that landed, in order.
+    signatures: Vec<Signature>,
+    /// Index of the first instruction in the failed transaction, with its error.
+    failed_at: Option<(usize, String)>,
+}
+
+/// A spending limit as it stands at a given slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Limit {
+    /// Most the limit allows: per window for recurring limits. One-off limits
+    /// are drawn down on-chain, so their cap is what is left.
+    cap: u64,
+    /// Amount spendable right now.
+    remaining: u64,
+    /// Window length in slots, for recurring limits.
+    window: Option<u64>,
+}
+
+impl Limit {
+    fn new(amount: u64, recurring: Option<&RecurringConfig>, slot: u64) -> Self {
+        Limit {
+            cap: amount,
+            remaining: available_at(amount, recurring, slot),
+            window: recurring.map(|r| r.window),
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.window {
+            Some(window) => write!(
+                f,
+                "up to {} per {} slots ({} remaining this window)",
+                self.cap, window, self.remaining
+            ),
+            None => write!(f, "up to {}", self.remaining),
+        }
+    }
+}
+
+/// Consolidated view of what a role may do, merged across its permissions.
+#[derive(Debug, Default)]
+struct EffectiveAllowances {
+    /// `All` or `AllButManageAuthority`: no spending or program limits apply.
+    unrestricted: bool,
+    /// Can add, remove, and update other authorities.
+    manage_authority: bool,
+    /// Lamports the role may transfer; `None` means no SOL transfers.
+    sol: Option<Limit>,
+    /// Lamports the role may transfer to specific destinations.
+    sol_destinations: HashMap<Pubkey, Limit>,
+    /// Amount the role may transfer, per token mint.
+    tokens: HashMap<Pubkey, Limit>,
+    /// Amount the role may transfer, per `(mint, destination)`.
+    token_destinations: HashMap<(Pubkey, Pubkey), Limit>,
+    /// Lamports the role may stake.
+    stake: Option<Limit>,
+    /// Can call any program (`ProgramAll`).
+    all_programs: bool,
+    /// Can call the programs on the on-chain curated list (`ProgramCurated`).
+    curated_programs: bool,
+    /// Specific programs the role may call.
+    programs: Vec<Pubkey>,
+    /// Permissions with no consolidated form (e.g. `SubAccount`), as written.
+    other: Vec<String>,
+}
+
+impl fmt::Display for EffectiveAllowances {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unrestricted {
+            writeln!(f, "Unrestricted")?;
+        }
+        if self.manage_authority {
+            writeln!(f, "Can manage authorities")?;
+        }
+        if let Some(limit) = &self.sol {
+            writeln!(f, "SOL (lamports): {}", limit)?;
+        }
+        for (destination, limit) in &self.sol_destinations {
+            writeln!(f, "SOL to {} (lamports): {}", destination, limit)?;
+        }
+        for (mint, limit) in &self.tokens {
+            writeln!(f, "Token {}: {}", mint, limit)?;
+        }
+        for ((mint, destination), limit) in &self.token_destinations {
+            writeln!(f, "Token {} to {}: {}", mint, destination, limit)?;
+        }
+        if let Some(limit) = &self.stake {
+            writeln!(f, "Stake (lamports): {}", limit)?;
+        }
+        if self.all_programs {
+            writeln!(f, "Programs: any")?;
+        } else {
+            if self.curated_programs {
+                writeln!(f, "Programs: curated list")?;
+            }
+            for program_id in &self.programs {
+                writeln!(f, "Program: {}", program_id)?;
+            }
+        }
+        for permission in &self.other {
+            writeln!(f, "Other: {}", permission)?;
+        }
+        Ok(())
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let authority = load_or_create_keypair("authority.json");
     println!("Authority: {}", authority.pubkey());
@@ -28,7 +218,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let rpc_client = RpcClient::new(RPC_URL);
 
     // Create a wallet for this example
-    let swig_id: [u8; 32] = rand::thread_rng().r#gen();
+    let swig_id = common::generate_swig_id();
     let mut wallet = SwigWallet::builder()
         .with_swig_id(swig_id)
         .with_client_role(Box::new(Ed25519ClientRole::new(authority.pubkey())))
@@ -101,6 +291,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     )?;
     println!("Added token manager: {}", token_manager.pubkey());
 
+    // Authority with a large permission set (split across transactions)
+    let treasury_manager = Keypair::new();
+    let treasury_permissions = (0..20)
+        .map(|_| Permission::Token {
+            mint: Pubkey::new_unique(), // Replace with real mints
+            amount: 1_000_000,
+            recurring: None,
+        })
+        .collect();
+    let (sigs, treasury_role_id) = add_authority_chunked(
+        &mut wallet,
+        AuthorityType::Ed25519,
+        treasury_manager.pubkey().as_ref(),
+        treasury_permissions,
+    )?;
+    println!(
+        "Added treasury manager as role {} in {} transactions",
+        treasury_role_id,
+        sigs.len()
+    );
+
     // =========================================================================
     // 3. VIEW ALL AUTHORITIES
     // =========================================================================
@@ -117,6 +328,49 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Project how the daily spender's recurring allowance refills
+    println!("\n=== Daily Spender Budget ===");
+
+    let current_slot = rpc_client.get_slot()?;
+    let daily_role = info
+        .roles
+        .iter()
+        .find(|r| r.authority_identity == daily_spender.pubkey().as_ref())
+        .expect("Daily spender role not found");
+    for perm in &daily_role.permissions {
+        if let Permission::Sol {
+            amount,
+            recurring: Some(recurring),
+        } = perm
+        {
+            let to_slot = current_slot + 2 * recurring.window;
+            let print_schedule = |recurring: &RecurringConfig| {
+                for (slot, elapsed, available) in
+                    allowance_schedule(*amount, recurring, current_slot, to_slot, SLOT_TIME)
+                {
+                    println!(
+                        "  +{:>5.1}h (slot {}): {} lamports available",
+                        elapsed.as_secs_f64() / 3600.0,
+                        slot,
+                        available
+                    );
+                }
+            };
+
+            println!("On-chain state:");
+            print_schedule(recurring);
+
+            // A fresh role has not spent anything, so the projection above is
+            // flat. Show what a partly used window would look like.
+            println!("Hypothetical (not on-chain): 3/4 spent, reset half a window ago:");
+            print_schedule(&RecurringConfig {
+                window: recurring.window,
+                last_reset: current_slot.saturating_sub(recurring.window / 2),
+                current_amount: amount / 4,
+            });
+        }
+    }
+
     // =========================================================================
     // 4. UPDATE AUTHORITY PERMISSIONS
     // =========================================================================
@@ -140,6 +394,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     )?;
     println!("Added permissions to role {}", spender_role_id);
 
+    // The spender now has two SOL limits; show the one the program applies
+    let current_slot = rpc_client.get_slot()?;
+    let spender_role = wallet
+        .get_info()?
+        .roles
+        .into_iter()
+        .find(|r| r.role_id == spender_role_id)
+        .expect("Spender role not found");
+    print!(
+        "Effective allowances:\n{}",
+        effective_allowances(&spender_role.permissions, current_slot)
+    );
+
     // Note: ReplaceAll is also available:
     // wallet.update_authority(
     //     spender_role_id,
@@ -171,7 +438,48 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Funded wallet with {} lamports", fund_amount);
 
     // =========================================================================
-    // 7. SIGN TRANSACTIONS
+    // 7. CHECK WHAT THE ACTIVE ROLE CAN DO
+    // =========================================================================
+    println!("\n=== Checking Active Role Permissions ===");
+
+    let current_slot = rpc_client.get_slot()?;
+    let permissions_of = |identity: &[u8]| {
+        info.roles
+            .iter()
+            .find(|r| r.authority_identity == identity)
+            .map(|r| r.permissions.as_slice())
+            .expect("Role not found")
+    };
+
+    // The wallet is acting as its creator, which holds `All`
+    let active_permissions = permissions_of(authority.pubkey().as_ref());
+    println!(
+        "Can transfer 0.5 SOL: {}",
+        can_transfer_sol(active_permissions, 500_000_000, current_slot)
+    );
+    println!(
+        "Can transfer 1000 of {}: {}",
+        token_mint,
+        can_transfer_token(active_permissions, &token_mint, 1000, current_slot)
+    );
+    println!(
+        "Can call the system program: {}",
+        can_call_program(active_permissions, &solana_sdk::system_program::id())
+    );
+
+    // The spender's first one-off limit (1 SOL) applies; the 0.5 SOL limit
+    // added in step 4 does not stack on top of it
+    let spender_permissions = permissions_of(spender_key.pubkey().as_ref());
+    for amount in [800_000_000, 1_200_000_000] {
+        println!(
+            "Spender can transfer {} lamports: {}",
+            amount,
+            can_transfer_sol(spender_permissions, amount, current_slot)
+        );
+    }
+
+    // =========================================================================
+    // 8. SIGN TRANSACTIONS
     // =========================================================================
     println!("\n=== Signing Transactions ===");
 
@@ -189,8 +497,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let sig = wallet.sign_v2(vec![ix1, ix2], None)?;
     println!("Multi-instruction tx: {}", sig);
 
+    // Pay many recipients, split into as few transactions as possible
+    let recipients: Vec<(Pubkey, u64)> = (0..15).map(|_| (Pubkey::new_unique(), 100)).collect();
+    let sigs = transfer_many(
+        &mut wallet,
+        &rpc_client,
+        authority.pubkey().as_ref(),
+        &recipients,
+    )?;
+    println!(
+        "Paid {} recipients in {} transactions",
+        recipients.len(),
+        sigs.len()
+    );
+
     // =========================================================================
-    // 8. SUB-ACCOUNT OPERATIONS (Advanced)
+    // 9. SUB-ACCOUNT OPERATIONS (Advanced)
     // =========================================================================
     // Sub-accounts allow creating derived addresses from the main wallet.
     // This feature requires specific setup - see SDK documentation.
@@ -207,7 +529,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // let sig = wallet.sign_with_sub_account(vec![sub_account_ix], None)?;
 
     // =========================================================================
-    // 9. SESSION MANAGEMENT (Advanced)
+    // 10. SESSION MANAGEMENT (Advanced)
     // =========================================================================
     // Sessions allow temporary delegation of signing authority.
     // This feature requires specific setup - see SDK documentation.
@@ -217,6 +539,51 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // let session_duration = 3600; // slots
     // wallet.create_session(session_key.pubkey(), session_duration)?;
 
+    // =========================================================================
+    // 11. TOKEN OPERATIONS (Advanced)
+    // =========================================================================
+    // Requires the wallet to hold tokens of a real SPL mint.
+    // Run with TOKEN_MINT=<mint address> to enable this section.
+    if let Ok(mint) = std::env::var("TOKEN_MINT") {
+        println!("\n=== Token Operations ===");
+
+        let mint: Pubkey = mint.parse()?;
+        let delegate = Keypair::new();
+
+        let sig = approve_token(
+            &mut wallet,
+            &rpc_client,
+            authority.pubkey().as_ref(),
+            &mint,
+            &delegate.pubkey(),
+            1_000,
+        )?;
+        println!("Approved {} as delegate: {}", delegate.pubkey(), sig);
+
+        let sig = revoke_token(&mut wallet, &rpc_client, &mint)?;
+        println!("Revoked delegate: {}", sig);
+
+        // Sweep the whole balance back to the authority. Passing `true`
+        // instead would also close the account in the same transaction.
+        let sig = transfer_all_token(
+            &mut wallet,
+            &rpc_client,
+            authority.pubkey().as_ref(),
+            &mint,
+            &authority.pubkey(),
+            false,
+        )?;
+        println!("Transferred entire token balance: {}", sig);
+
+        // Close the now-empty token account and reclaim its rent
+        let owner = wallet.get_info()?.swig_wallet_address;
+        let token_program = token_program_of(&rpc_client, &mint)?;
+        let token_account =
+            get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+        let sig = close_token_account(&mut wallet, &rpc_client, &token_account, &owner)?;
+        println!("Closed token account {}: {}", token_account, sig);
+    }
+
     println!("\n=== Done ===");
     Ok(())
 }
@@ -244,6 +611,574 @@ fn fund_wallet(
     Ok(())
 }
 
+/// Add an authority whose permission set may not fit in a single transaction.
+///
+/// Permissions are packed in order by their encoded size. The first chunk is
+/// sent with `add_authority`; the rest are appended to the new role with
+/// `update_authority(AddActions)`. The role is valid (holding a subset of its
+/// permissions) after every transaction. A set that fits is sent as a single
+/// `add_authority`. Returns all transaction signatures and the new role ID.
+fn add_authority_chunked(
+    wallet: &mut SwigWallet,
+    authority_type: AuthorityType,
+    identity: &[u8],
+    permissions: Vec<Permission>,
+) -> Result<(Vec<Signature>, u32), ChunkedAddError> {
+    let fail = |role_id, signatures, error: String| ChunkedAddError {
+        role_id,
+        signatures,
+        error,
+    };
+
+    let mut chunks = pack_permissions(identity, permissions)
+        .map_err(|index| fail(None, vec![], format!("permission {} is too large", index)))?
+        .into_iter()
+        .map(|(_, chunk)| chunk);
+
+    let first = chunks.next().unwrap_or_default();
+    let mut signatures = vec![
+        wallet
+            .add_authority(authority_type, identity, first)
+            .map_err(|err| fail(None, vec![], err.to_string()))?,
+    ];
+
+    // The newest role with this identity is the one we just added
+    let role_id = wallet
+        .get_info()
+        .map_err(|err| err.to_string())
+        .and_then(|info| {
+            info.roles
+                .iter()
+                .filter(|r| r.authority_identity == identity)
+                .map(|r| r.role_id)
+                .max()
+                .ok_or_else(|| "added role not found".to_string())
+        })
+        .map_err(|err| fail(None, signatures.clone(), err))?;
+
+    for chunk in chunks {
+        match wallet.update_authority(role_id, UpdateAuthorityData::AddActions(chunk)) {
+            Ok(signature) => signatures.push(signature),
+            Err(err) => return Err(fail(Some(role_id), signatures, err.to_string())),
+        }
+    }
+
+    Ok((signatures, role_id))
+}
+
+/// Pack permissions for the role `identity` into add/update-authority sized chunks.
+///
+/// Returns the chunks as `pack_by_size` does, or the index of a permission
+/// that does not fit on its own.
+fn pack_permissions(
+    identity: &[u8],
+    permissions: Vec<Permission>,
+) -> Result<Vec<(usize, Vec<Permission>)>, usize> {
+    let budget = PACKET_DATA_SIZE - AUTHORITY_TX_OVERHEAD - identity.len();
+    pack_by_size(permissions, |chunk| {
+        chunk.iter().map(permission_size).sum::<usize>() <= budget
+    })
+}
+
+/// Worst-case encoded size of a permission in an add/update-authority instruction.
+fn permission_size(permission: &Permission) -> usize {
+    // One-off limits store the amount; recurring limits add window,
+    // last reset and current amount.
+    let limit = |recurring: &Option<RecurringConfig>| if recurring.is_some() { 32 } else { 8 };
+    let body = match permission {
+        Permission::All
+        | Permission::AllButManageAuthority
+        | Permission::ManageAuthority
+        | Permission::ProgramAll
+        | Permission::ProgramCurated => 0,
+        Permission::Sol { recurring, .. } | Permission::Stake { recurring, .. } => limit(recurring),
+        Permission::SolDestination { recurring, .. } | Permission::Token { recurring, .. } => {
+            32 + limit(recurring)
+        }
+        Permission::TokenDestination { recurring, .. } => 64 + limit(recurring),
+        Permission::Program { .. } => 32,
+        // Sub-account address plus bookkeeping, and anything newer: assume large
+        _ => 64,
+    };
+    PERMISSION_HEADER_SIZE + body
+}
+
+/// Greedily pack `items`, in order, into chunks accepted by `fits`.
+///
+/// Returns `(index of first item, items)` per chunk, or the index of an item
+/// that does not fit even on its own.
+fn pack_by_size<T>(
+    items: Vec<T>,
+    fits: impl Fn(&[T]) -> bool,
+) -> Result<Vec<(usize, Vec<T>)>, usize> {
+    let mut chunks: Vec<(usize, Vec<T>)> = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        if let Some((_, chunk)) = chunks.last_mut() {
+            chunk.push(item);
+            if fits(chunk) {
+                continue;
+            }
+            let item = chunk.pop().expect("just pushed");
+            chunks.push((index, vec![item]));
+        } else {
+            chunks.push((index, vec![item]));
+        }
+        if !fits(&chunks.last().expect("just pushed").1) {
+            return Err(index);
+        }
+    }
+    Ok(chunks)
+}
+
+/// Transfer SOL from the swig wallet to many recipients.
+///
+/// Recipients are paid atomically in a single transaction when they fit;
+/// longer lists are split by `sign_v2_multi` and submitted in order. Before
+/// anything is sent, the total is checked against the wallet balance and
+/// against the SOL limits of the role acting as `active_identity`, including
+/// what is left of a recurring window, so a list that would fail partway
+/// through is rejected up front.
+///
+/// Only the role's general `Sol` limit is considered (see
+/// `can_transfer_sol`): a role whose only SOL permissions are
+/// `SolDestination` limits for these recipients is rejected here even though
+/// the program would allow the transfers.
+fn transfer_many(
+    wallet: &mut SwigWallet,
+    rpc_client: &RpcClient,
+    active_identity: &[u8],
+    recipients: &[(Pubkey, u64)],
+) -> Result<Vec<Signature>, Box<dyn std::error::Error>> {
+    let info = wallet.get_info()?;
+    let total = recipients
+        .iter()
+        .try_fold(0u64, |total, (_, amount)| total.checked_add(*amount))
+        .ok_or("Total transfer amount overflows u64")?;
+    if total > info.wallet_balance {
+        return Err(format!(
+            "Insufficient balance: need {} lamports, have {}",
+            total, info.wallet_balance
+        )
+        .into());
+    }
+
+    let permissions = &info
+        .roles
+        .iter()
+        .find(|r| r.authority_identity == active_identity)
+        .ok_or("Active role not found")?
+        .permissions;
+    if !can_transfer_sol(permissions, total, rpc_client.get_slot()?) {
+        return Err(format!(
+            "Total of {} lamports exceeds the active role's SOL limit",
+            total
+        )
+        .into());
+    }
+
+    let instructions = recipients
+        .iter()
+        .map(|(recipient, amount)| {
+            system_instruction::transfer(&info.swig_wallet_address, recipient, *amount)
+        })
+        .collect();
+
+    let result = sign_v2_multi(wallet, instructions)?;
+    match result.failed_at {
+        Some((index, error)) => Err(format!(
+            "Payment to recipient {} failed after {} transactions: {}",
+            index,
+            result.signatures.len(),
+            error
+        )
+        .into()),
+        None => Ok(result.signatures),
+    }
+}
+
+/// Sign and submit an instruction list that may not fit in one transaction.
+///
+/// Instructions are packed greedily, in order, into transactions whose
+/// estimated wrapped size (`wrapped_size`) stays under the packet limit,
+/// then submitted one by one. An instruction too large for any transaction
+/// is rejected before anything is sent. Submission stops at the first
+/// failure, reporting the index of the first instruction in the failed
+/// transaction.
+fn sign_v2_multi(
+    wallet: &mut SwigWallet,
+    instructions: Vec<Instruction>,
+) -> Result<MultiSignResult, Box<dyn std::error::Error>> {
+    let payer = wallet.get_info()?.swig_wallet_address;
+
+    let fits = |chunk: &[Instruction]| wrapped_size(chunk, &payer) <= PACKET_DATA_SIZE;
+    let chunks = pack_by_size(instructions, fits).map_err(|index| {
+        format!(
+            "Instruction {} does not fit in a transaction even on its own",
+            index
+        )
+    })?;
+
+    let mut signatures = Vec::with_capacity(chunks.len());
+    for (start, chunk) in chunks {
+        match wallet.sign_v2(chunk, None) {
+            Ok(signature) => signatures.push(signature),
+            Err(err) => {
+                return Ok(MultiSignResult {
+                    signatures,
+                    failed_at: Some((start, err.to_string())),
+                });
+            }
+        }
+    }
+
+    Ok(MultiSignResult {
+        signatures,
+        failed_at: None,
+    })
+}
+
+/// Worst-case size of the `sign_v2` transaction wrapping `instructions`.
+///
+/// Measures the instructions as a bare message paid by `swig_wallet`, then
+/// adds `SIGN_V2_TX_OVERHEAD` and what the swig instruction adds per inner
+/// instruction: an account index for the program and each account it
+/// references, and a fixed-width data length.
+fn wrapped_size(instructions: &[Instruction], swig_wallet: &Pubkey) -> usize {
+    let inner_message_size = Message::new(instructions, Some(swig_wallet))
+        .serialize()
+        .len();
+    let per_instruction: usize = instructions.iter().map(|ix| ix.accounts.len() + 2).sum();
+    inner_message_size + SIGN_V2_TX_OVERHEAD + per_instruction
+}
+
+/// Approve `delegate` to move up to `amount` of the wallet's `mint` tokens.
+///
+/// Builds an `approve` on the wallet's associated token account for the
+/// mint's token program (SPL Token or Token-2022), signed through the swig
+/// wallet. The approval counts against the role's token limit, so `amount`
+/// is checked against the limit of the role acting as `active_identity`
+/// before sending.
+fn approve_token(
+    wallet: &mut SwigWallet,
+    rpc_client: &RpcClient,
+    active_identity: &[u8],
+    mint: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let token_program = token_program_of(rpc_client, mint)?;
+    let info = wallet.get_info()?;
+    let owner = info.swig_wallet_address;
+
+    let permissions = &info
+        .roles
+        .iter()
+        .find(|r| r.authority_identity == active_identity)
+        .ok_or("Active role not found")?
+        .permissions;
+    if !can_transfer_token(permissions, mint, amount, rpc_client.get_slot()?) {
+        return Err(format!(
+            "Approval of {} exceeds the active role's limit for {}",
+            amount, mint
+        )
+        .into());
+    }
+
+    let source = get_associated_token_address_with_program_id(&owner, mint, &token_program);
+    let mut approve_ix =
+        spl_token::instruction::approve(&spl_token::id(), &source, delegate, &owner, &[], amount)?;
+    approve_ix.program_id = token_program;
+    Ok(wallet.sign_v2(vec![approve_ix], None)?)
+}
+
+/// Revoke any delegate on the wallet's associated token account for `mint`.
+fn revoke_token(
+    wallet: &mut SwigWallet,
+    rpc_client: &RpcClient,
+    mint: &Pubkey,
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let token_program = token_program_of(rpc_client, mint)?;
+    let owner = wallet.get_info()?.swig_wallet_address;
+    let source = get_associated_token_address_with_program_id(&owner, mint, &token_program);
+    let mut revoke_ix = spl_token::instruction::revoke(&spl_token::id(), &source, &owner, &[])?;
+    revoke_ix.program_id = token_program;
+    Ok(wallet.sign_v2(vec![revoke_ix], None)?)
+}
+
+/// Close an empty token account owned by the swig wallet, sending its rent
+/// to `rent_destination`.
+///
+/// Refuses accounts that still hold tokens, since closing them would fail
+/// on-chain. Works with SPL Token and Token-2022 accounts.
+fn close_token_account(
+    wallet: &mut SwigWallet,
+    rpc_client: &RpcClient,
+    token_account: &Pubkey,
+    rent_destination: &Pubkey,
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let token_program = token_program_of(rpc_client, token_account)?;
+    let balance = rpc_client.get_token_account_balance(token_account)?;
+    if balance.amount != "0" {
+        return Err(format!(
+            "Token account {} still holds {} tokens; transfer them before closing",
+            token_account, balance.amount
+        )
+        .into());
+    }
+
+    let owner = wallet.get_info()?.swig_wallet_address;
+    let mut close_ix = spl_token::instruction::close_account(
+        &spl_token::id(),
+        token_account,
+        rent_destination,
+        &owner,
+        &[],
+    )?;
+    close_ix.program_id = token_program;
+    Ok(wallet.sign_v2(vec![close_ix], None)?)
+}
+
+/// The token program that owns `account` (a mint or token account): SPL
+/// Token or Token-2022.
+///
+/// Token-2022 keeps the SPL Token encoding for the base instructions used
+/// here (approve, revoke, transfer_checked, close_account), so they are
+/// built with `spl_token` and pointed at the returned program.
+fn token_program_of(
+    rpc_client: &RpcClient,
+    account: &Pubkey,
+) -> Result<Pubkey, Box<dyn std::error::Error>> {
+    let owner = rpc_client.get_account(account)?.owner;
+    if owner == spl_token::id() || owner == TOKEN_2022_PROGRAM_ID {
+        Ok(owner)
+    } else {
+        Err(format!("{} is not a token account (owned by {})", account, owner).into())
+    }
+}
+
+/// Transfer the wallet's entire `mint` balance to `to`'s associated token account.
+///
+/// Reads the current balance and decimals of the wallet's token account and
+/// sends everything with `transfer_checked`, creating `to`'s token account
+/// first if needed (rent paid by the swig wallet). Works with SPL Token and
+/// Token-2022 mints. The amount is checked against the token limit of the
+/// role acting as `active_identity` before sending. With `close_source`, the
+/// emptied account is closed in the same transaction and its rent returned
+/// to the swig wallet.
+fn transfer_all_token(
+    wallet: &mut SwigWallet,
+    rpc_client: &RpcClient,
+    active_identity: &[u8],
+    mint: &Pubkey,
+    to: &Pubkey,
+    close_source: bool,
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let token_program = token_program_of(rpc_client, mint)?;
+    let info = wallet.get_info()?;
+    let owner = info.swig_wallet_address;
+    let source = get_associated_token_address_with_program_id(&owner, mint, &token_program);
+    let destination = get_associated_token_address_with_program_id(to, mint, &token_program);
+
+    let balance = rpc_client.get_token_account_balance(&source)?;
+    let amount: u64 = balance.amount.parse()?;
+
+    let permissions = &info
+        .roles
+        .iter()
+        .find(|r| r.authority_identity == active_identity)
+        .ok_or("Active role not found")?
+        .permissions;
+    if !can_transfer_token(permissions, mint, amount, rpc_client.get_slot()?) {
+        return Err(format!(
+            "Balance of {} exceeds the active role's limit for {}",
+            amount, mint
+        )
+        .into());
+    }
+
+    let mut transfer_ix = spl_token::instruction::transfer_checked(
+        &spl_token::id(),
+        &source,
+        mint,
+        &destination,
+        &owner,
+        &[],
+        amount,
+        balance.decimals,
+    )?;
+    transfer_ix.program_id = token_program;
+    let mut instructions = vec![
+        create_associated_token_account_idempotent(&owner, to, mint, &token_program),
+        transfer_ix,
+    ];
+    if close_source {
+        let mut close_ix =
+            spl_token::instruction::close_account(&spl_token::id(), &source, &owner, &owner, &[])?;
+        close_ix.program_id = token_program;
+        instructions.push(close_ix);
+    }
+    Ok(wallet.sign_v2(instructions, None)?)
+}
+
+/// Project the available allowance of a recurring limit over a slot range.
+///
+/// Returns `(slot, time since from_slot, available)` for `from_slot` and for
+/// every slot in range where the allowance changes, with time derived from
+/// `slot_time`. The remaining `current_amount` is available until the window
+/// elapses, after which the program refills the limit to the full `amount`.
+/// Assumes no further spending within the range, so after the refill the
+/// allowance stays full.
+fn allowance_schedule(
+    amount: u64,
+    recurring: &RecurringConfig,
+    from_slot: u64,
+    to_slot: u64,
+    slot_time: Duration,
+) -> Vec<(u64, Duration, u64)> {
+    if from_slot > to_slot {
+        return Vec::new();
+    }
+    let point = |slot: u64| {
+        let slots = u32::try_from(slot - from_slot).unwrap_or(u32::MAX);
+        let elapsed = slot_time.saturating_mul(slots);
+        (slot, elapsed, available_at(amount, Some(recurring), slot))
+    };
+
+    let mut schedule = vec![point(from_slot)];
+    // First slot for which `slot - last_reset > window`
+    let refill_slot = recurring
+        .last_reset
+        .saturating_add(recurring.window)
+        .saturating_add(1);
+    if refill_slot > from_slot && refill_slot <= to_slot && amount != recurring.current_amount {
+        schedule.push(point(refill_slot));
+    }
+    schedule
+}
+
+/// Amount a limit allows at `slot`.
+///
+/// One-off limits hold their remaining `amount`. Recurring limits hold
+/// `current_amount` until the window elapses, then refill to `amount`.
+fn available_at(amount: u64, recurring: Option<&RecurringConfig>, slot: u64) -> u64 {
+    match recurring {
+        Some(recurring) if slot.saturating_sub(recurring.last_reset) > recurring.window => amount,
+        Some(recurring) => recurring.current_amount,
+        None => amount,
+    }
+}
+
+/// Merge a role's permissions into consolidated limits at `slot`.
+///
+/// Limits are not summed. Like the program, when a role has several limits
+/// of one kind (e.g. two `Sol` permissions, or two for the same mint) the
+/// first one-off limit applies, and a recurring limit only applies when
+/// there is no one-off limit.
+fn effective_allowances(permissions: &[Permission], slot: u64) -> EffectiveAllowances {
+    // Keep `existing` unless it is recurring and `limit` is one-off
+    fn merge(existing: &mut Limit, limit: Limit) {
+        if existing.window.is_some() && limit.window.is_none() {
+            *existing = limit;
+        }
+    }
+
+    let mut allowances = EffectiveAllowances::default();
+    for perm in permissions {
+        match perm {
+            Permission::All => {
+                allowances.unrestricted = true;
+                allowances.manage_authority = true;
+            }
+            Permission::AllButManageAuthority => allowances.unrestricted = true,
+            Permission::ManageAuthority => allowances.manage_authority = true,
+            Permission::Sol { amount, recurring } => {
+                let limit = Limit::new(*amount, recurring.as_ref(), slot);
+                merge(allowances.sol.get_or_insert(limit), limit);
+            }
+            Permission::SolDestination {
+                destination,
+                amount,
+                recurring,
+            } => {
+                let limit = Limit::new(*amount, recurring.as_ref(), slot);
+                merge(
+                    allowances
+                        .sol_destinations
+                        .entry(*destination)
+                        .or_insert(limit),
+                    limit,
+                );
+            }
+            Permission::Token {
+                mint,
+                amount,
+                recurring,
+            } => {
+                let limit = Limit::new(*amount, recurring.as_ref(), slot);
+                merge(allowances.tokens.entry(*mint).or_insert(limit), limit);
+            }
+            Permission::TokenDestination {
+                mint,
+                destination,
+                amount,
+                recurring,
+            } => {
+                let limit = Limit::new(*amount, recurring.as_ref(), slot);
+                merge(
+                    allowances
+                        .token_destinations
+                        .entry((*mint, *destination))
+                        .or_insert(limit),
+                    limit,
+                );
+            }
+            Permission::Stake { amount, recurring } => {
+                let limit = Limit::new(*amount, recurring.as_ref(), slot);
+                merge(allowances.stake.get_or_insert(limit), limit);
+            }
+            Permission::ProgramAll => allowances.all_programs = true,
+            Permission::ProgramCurated => allowances.curated_programs = true,
+            Permission::Program { program_id } => allowances.programs.push(*program_id),
+            // `SubAccount`, and kinds added to the SDK after this example
+            _ => allowances.other.push(format!("{:?}", perm)),
+        }
+    }
+    allowances
+}
+
+/// Whether a role with these permissions can transfer `amount` lamports at `slot`.
+///
+/// Uses the same limit as `effective_allowances`, so the two always agree.
+/// Only the general `Sol` limit counts; `SolDestination` limits depend on
+/// the recipient and are not considered.
+fn can_transfer_sol(permissions: &[Permission], amount: u64, slot: u64) -> bool {
+    let allowances = effective_allowances(permissions, slot);
+    allowances.unrestricted
+        || allowances
+            .sol
+            .is_some_and(|limit| limit.remaining >= amount)
+}
+
+/// Whether a role with these permissions can transfer `amount` of `mint` at `slot`.
+///
+/// Uses the same limit as `effective_allowances`, so the two always agree.
+fn can_transfer_token(permissions: &[Permission], mint: &Pubkey, amount: u64, slot: u64) -> bool {
+    let allowances = effective_allowances(permissions, slot);
+    allowances.unrestricted
+        || allowances
+            .tokens
+            .get(mint)
+            .is_some_and(|limit| limit.remaining >= amount)
+}
+
+/// Whether a role with these permissions can call `program_id`.
+///
+/// `ProgramCurated` is not considered, since the curated list lives on-chain.
+fn can_call_program(permissions: &[Permission], program_id: &Pubkey) -> bool {
+    let allowances = effective_allowances(permissions, 0);
+    allowances.unrestricted || allowances.all_programs || allowances.programs.contains(program_id)
+}
+
 /// Demonstrate all permission types
 fn demonstrate_permission_types() {
     println!("Available Permission Types:");
@@ -306,3 +1241,213 @@ fn load_or_create_keypair(path: &str) -> Keypair {
         keypair
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recurring(window: u64, last_reset: u64, current_amount: u64) -> RecurringConfig {
+        RecurringConfig {
+            window,
+            last_reset,
+            current_amount,
+        }
+    }
+
+    fn sol(amount: u64, recurring: Option<RecurringConfig>) -> Permission {
+        Permission::Sol { amount, recurring }
+    }
+
+    #[test]
+    fn effective_allowances_prefers_the_first_one_off_limit() {
+        let daily = Some(recurring(10, 100, 25));
+        let allowances =
+            effective_allowances(&[sol(100, daily), sol(1_000, None), sol(500, None)], 105);
+        assert_eq!(
+            allowances.sol,
+            Some(Limit {
+                cap: 1_000,
+                remaining: 1_000,
+                window: None,
+            })
+        );
+        assert!(!allowances.unrestricted);
+    }
+
+    #[test]
+    fn effective_allowances_reports_cap_and_remaining_for_recurring_limits() {
+        let mint = Pubkey::new_unique();
+        let token = |recurring| Permission::Token {
+            mint,
+            amount: 100,
+            recurring,
+        };
+        let permissions = [token(Some(recurring(10, 100, 25)))];
+
+        let in_window = effective_allowances(&permissions, 110);
+        assert_eq!(
+            in_window.tokens[&mint],
+            Limit {
+                cap: 100,
+                remaining: 25,
+                window: Some(10),
+            }
+        );
+        let after_window = effective_allowances(&permissions, 111);
+        assert_eq!(after_window.tokens[&mint].remaining, 100);
+        assert!(after_window.sol.is_none());
+    }
+
+    #[test]
+    fn effective_allowances_keeps_every_permission_kind() {
+        let destination = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let allowances = effective_allowances(
+            &[
+                Permission::AllButManageAuthority,
+                Permission::SolDestination {
+                    destination,
+                    amount: 7,
+                    recurring: None,
+                },
+                Permission::Stake {
+                    amount: 9,
+                    recurring: None,
+                },
+                Permission::Program { program_id },
+                Permission::ProgramCurated,
+                Permission::SubAccount {
+                    sub_account: [1; 32],
+                },
+            ],
+            0,
+        );
+        assert!(allowances.unrestricted);
+        assert!(!allowances.manage_authority);
+        assert_eq!(allowances.sol_destinations[&destination].remaining, 7);
+        assert_eq!(allowances.stake.map(|l| l.cap), Some(9));
+        assert_eq!(allowances.programs, vec![program_id]);
+        assert!(allowances.curated_programs);
+        assert_eq!(allowances.other.len(), 1);
+    }
+
+    #[test]
+    fn can_transfer_sol_agrees_with_effective_allowances() {
+        // 1 SOL applies; the later 0.5 SOL limit does not add to it
+        let permissions = [sol(1_000_000_000, None), sol(500_000_000, None)];
+        assert!(can_transfer_sol(&permissions, 1_000_000_000, 0));
+        assert!(!can_transfer_sol(&permissions, 1_200_000_000, 0));
+        assert!(can_transfer_sol(&[Permission::All], u64::MAX, 0));
+        assert!(!can_transfer_sol(&[Permission::ManageAuthority], 1, 0));
+    }
+
+    #[test]
+    fn can_transfer_token_uses_the_window_remainder() {
+        let mint = Pubkey::new_unique();
+        let permissions = [Permission::Token {
+            mint,
+            amount: 100,
+            recurring: Some(recurring(10, 100, 25)),
+        }];
+        assert!(can_transfer_token(&permissions, &mint, 25, 110));
+        assert!(!can_transfer_token(&permissions, &mint, 26, 110));
+        assert!(can_transfer_token(&permissions, &mint, 100, 111));
+        let other_mint = Pubkey::new_unique();
+        assert!(!can_transfer_token(&permissions, &other_mint, 1, 111));
+    }
+
+    #[test]
+    fn permissions_are_packed_within_the_authority_budget() {
+        let identity = Pubkey::new_unique();
+        // Every fourth limit is recurring, which encodes larger
+        let permissions: Vec<Permission> = (0..40)
+            .map(|i| Permission::Token {
+                mint: Pubkey::new_unique(),
+                amount: 1_000,
+                recurring: (i % 4 == 0).then(|| recurring(216_000, 0, 0)),
+            })
+            .collect();
+        let chunks = pack_permissions(identity.as_ref(), permissions).expect("permissions fit");
+
+        // 72 + 3 * 48 bytes per group of four; three groups fit in 656 bytes
+        let budget = PACKET_DATA_SIZE - AUTHORITY_TX_OVERHEAD - identity.as_ref().len();
+        assert_eq!(budget, 656);
+        let starts_and_lens: Vec<(usize, usize)> = chunks
+            .iter()
+            .map(|(start, chunk)| (*start, chunk.len()))
+            .collect();
+        assert_eq!(starts_and_lens, vec![(0, 12), (12, 12), (24, 12), (36, 4)]);
+        for (_, chunk) in &chunks {
+            assert!(chunk.iter().map(permission_size).sum::<usize>() <= budget);
+        }
+    }
+
+    #[test]
+    fn sign_v2_chunks_keep_order_and_fit() {
+        let swig_wallet = Pubkey::new_unique();
+        let transfers: Vec<Instruction> = (0..15)
+            .map(|_| system_instruction::transfer(&swig_wallet, &Pubkey::new_unique(), 100))
+            .collect();
+        let fits = |chunk: &[Instruction]| wrapped_size(chunk, &swig_wallet) <= PACKET_DATA_SIZE;
+        let chunks = pack_by_size(transfers.clone(), fits).expect("transfers fit");
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|(_, chunk)| fits(chunk)));
+        let mut start = 0;
+        for (index, chunk) in &chunks {
+            assert_eq!(*index, start);
+            assert_eq!(chunk[..], transfers[start..start + chunk.len()]);
+            start += chunk.len();
+        }
+        assert_eq!(start, transfers.len());
+    }
+
+    #[test]
+    fn sign_v2_chunks_reject_an_oversized_instruction() {
+        let swig_wallet = Pubkey::new_unique();
+        let mut oversized = system_instruction::transfer(&swig_wallet, &Pubkey::new_unique(), 1);
+        oversized.data = vec![0; PACKET_DATA_SIZE];
+        let small = system_instruction::transfer(&swig_wallet, &Pubkey::new_unique(), 1);
+        let fits = |chunk: &[Instruction]| wrapped_size(chunk, &swig_wallet) <= PACKET_DATA_SIZE;
+        assert_eq!(pack_by_size(vec![small, oversized], fits), Err(1));
+    }
+
+    #[test]
+    fn available_at_refills_only_after_window_elapses() {
+        let config = recurring(10, 100, 25);
+        assert_eq!(available_at(100, Some(&config), 105), 25);
+        // `slot - last_reset == window` is still inside the window
+        assert_eq!(available_at(100, Some(&config), 110), 25);
+        assert_eq!(available_at(100, Some(&config), 111), 100);
+        // A slot before the last reset never refills
+        assert_eq!(available_at(100, Some(&config), 50), 25);
+        assert_eq!(available_at(100, None, 0), 100);
+    }
+
+    #[test]
+    fn allowance_schedule_marks_the_refill() {
+        let config = recurring(10, 100, 25);
+        let slot_time = Duration::from_millis(400);
+        assert_eq!(
+            allowance_schedule(100, &config, 105, 130, slot_time),
+            vec![(105, Duration::ZERO, 25), (111, slot_time * 6, 100)]
+        );
+    }
+
+    #[test]
+    fn allowance_schedule_without_refill_in_range() {
+        let config = recurring(10, 100, 25);
+        let slot_time = Duration::from_millis(400);
+        // Range ends exactly on the window boundary
+        assert_eq!(
+            allowance_schedule(100, &config, 105, 110, slot_time),
+            vec![(105, Duration::ZERO, 25)]
+        );
+        // Window already elapsed: full from the start
+        assert_eq!(
+            allowance_schedule(100, &config, 111, 200, slot_time),
+            vec![(111, Duration::ZERO, 100)]
+        );
+        assert!(allowance_schedule(100, &config, 200, 100, slot_time).is_empty());
+    }
+}